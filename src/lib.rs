@@ -1,8 +1,11 @@
+use npyz::WriterBuilder;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::default::Default;
-use std::fs::{create_dir_all, remove_dir_all};
-use std::io::{BufReader, BufWriter};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 // metadata
 const EXDIR_METANAME: &str = "exdir";
@@ -12,6 +15,7 @@ const VERSION_METANAME: &str = "version";
 // filenames
 const META_FILENAME: &str = "exdir.yaml";
 const ATTRIBUTES_FILENAME: &str = "attributes.yaml";
+const DATA_FILENAME: &str = "data.npy";
 const RAW_FOLDER_NAME: &str = "__raw__";
 
 // typenames
@@ -19,7 +23,170 @@ const RAW_FOLDER_NAME: &str = "__raw__";
 // const GROUP_TYPENAME: &str = "group";
 // const FILE_TYPENAME: &str = "file";
 
+#[derive(Debug, thiserror::Error)]
+enum ExdirError {
+    #[error("IO mode '{0}' not recognized, mode must be one of {1:?}")]
+    InvalidMode(String, Vec<&'static str>),
+    #[error("path '{0:?}' already exists")]
+    PathExists(PathBuf),
+    #[error("path '{0:?}' already exists, but is not a valid exdir file")]
+    NotAnExdirFile(PathBuf),
+    #[error("path '{0:?}' does not exist")]
+    FileNotFound(PathBuf),
+    #[error("create_dataset needs either data or a fill_value")]
+    MissingData,
+    #[error("'{0}' is not a valid exdir object name")]
+    InvalidName(String),
+    #[error("an object named '{0}' already exists in this directory (case-insensitive match)")]
+    NameCollision(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+/// Every filesystem operation the store needs goes through this trait, so
+/// that a real backend (`RealFs`) and an in-memory test backend (`FakeFs`)
+/// can be swapped in behind the same `Object`/`File`/`HasLeaves` code.
+trait Fs: std::fmt::Debug {
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()>;
+    fn remove_dir(&self, path: &Path) -> std::io::Result<()>;
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()>;
+    fn exists(&self, path: &Path) -> bool;
+    fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()>;
+    fn list_dir(&self, path: &Path) -> std::io::Result<Vec<String>>;
+}
+
+#[derive(Debug, Clone, Default)]
+struct RealFs;
+
+impl Fs for RealFs {
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::remove_dir_all(path)
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        // Write-then-sync so callers building atomic (write temp + rename)
+        // semantics on top of this trait get a durable temp file.
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(contents)?;
+        file.sync_all()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn list_dir(&self, path: &Path) -> std::io::Result<Vec<String>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        std::fs::read_dir(path)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect()
+    }
+}
+
+/// An in-memory `Fs` backend for tests: no real paths are touched, so
+/// fixtures can exercise group/dataset creation without a `TempDir`.
+#[derive(Debug, Default)]
+struct FakeFs {
+    dirs: RefCell<BTreeSet<PathBuf>>,
+    files: RefCell<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+impl Fs for FakeFs {
+    fn create_dir_all(&self, path: &Path) -> std::io::Result<()> {
+        let mut dirs = self.dirs.borrow_mut();
+        let mut built = PathBuf::new();
+        for component in path.components() {
+            built.push(component);
+            dirs.insert(built.clone());
+        }
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> std::io::Result<()> {
+        self.dirs.borrow_mut().retain(|d| !d.starts_with(path));
+        self.files.borrow_mut().retain(|f, _| !f.starts_with(path));
+        Ok(())
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+        self.files.borrow().get(path).cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{:?} not found in FakeFs", path),
+            )
+        })
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent)?;
+        }
+        self.files
+            .borrow_mut()
+            .insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.dirs.borrow().contains(path) || self.files.borrow().contains_key(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()> {
+        let removed_file = self.files.borrow_mut().remove(from);
+        if let Some(contents) = removed_file {
+            self.files.borrow_mut().insert(to.to_path_buf(), contents);
+            return Ok(());
+        }
+        if self.dirs.borrow_mut().remove(from) {
+            self.dirs.borrow_mut().insert(to.to_path_buf());
+            return Ok(());
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{:?} not found in FakeFs", from),
+        ))
+    }
+
+    fn list_dir(&self, path: &Path) -> std::io::Result<Vec<String>> {
+        let mut names = BTreeSet::new();
+        for dir in self.dirs.borrow().iter() {
+            if dir.parent() == Some(path) {
+                if let Some(name) = dir.file_name() {
+                    names.insert(name.to_string_lossy().into_owned());
+                }
+            }
+        }
+        for file in self.files.borrow().keys() {
+            if file.parent() == Some(path) {
+                if let Some(name) = file.file_name() {
+                    names.insert(name.to_string_lossy().into_owned());
+                }
+            }
+        }
+        Ok(names.into_iter().collect())
+    }
+}
+
+#[derive(Debug)]
 struct Object {
+    fs: Rc<dyn Fs>,
     root_directory: PathBuf,
     object_name: String,
     parent_path: PathBuf,
@@ -27,6 +194,7 @@ struct Object {
     relative_name: String,
     name: PathBuf,
     file: Option<std::fs::File>,
+    naming_rule: NamingRule,
 }
 
 impl Object {
@@ -35,6 +203,8 @@ impl Object {
         parent_path: &Path,
         object_name: &str,
         file: Option<std::fs::File>,
+        fs: Rc<dyn Fs>,
+        naming_rule: NamingRule,
     ) -> Self {
         let object_name = String::from(object_name);
         let relative_path = parent_path.join(object_name.clone());
@@ -44,6 +214,7 @@ impl Object {
         }
         let name = PathBuf::from("/").join(relative_name.clone());
         Object {
+            fs,
             root_directory: root_directory.to_path_buf(),
             object_name,
             parent_path: parent_path.to_path_buf(),
@@ -51,30 +222,284 @@ impl Object {
             relative_name,
             name,
             file,
+            naming_rule,
+        }
+    }
+
+    fn directory(&self) -> PathBuf {
+        self.root_directory.join(&self.relative_path)
+    }
+
+    fn attrs(&self) -> Attributes {
+        Attributes {
+            fs: self.fs.clone(),
+            directory: self.directory(),
         }
     }
 }
 
+/// A lazily-loaded view over an object's `attributes.yaml`, mirroring the
+/// way `exdir.yaml` metadata is read and written.
+struct Attributes {
+    fs: Rc<dyn Fs>,
+    directory: PathBuf,
+}
+
+impl Attributes {
+    fn path(&self) -> PathBuf {
+        self.directory.join(ATTRIBUTES_FILENAME)
+    }
+
+    fn load(&self) -> Result<BTreeMap<String, serde_yaml::Value>, ExdirError> {
+        if !self.fs.exists(&self.path()) {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_yaml::from_slice(&self.fs.read(&self.path())?)?)
+    }
+
+    fn save(&self, map: &BTreeMap<String, serde_yaml::Value>) -> Result<(), ExdirError> {
+        write_yaml_atomically(self.fs.as_ref(), &self.path(), map)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<serde_yaml::Value>, ExdirError> {
+        Ok(self.load()?.get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: serde_yaml::Value) -> Result<(), ExdirError> {
+        let mut map = self.load()?;
+        map.insert(key.to_string(), value);
+        self.save(&map)
+    }
+
+    fn contains(&self, key: &str) -> Result<bool, ExdirError> {
+        Ok(self.load()?.contains_key(key))
+    }
+
+    fn remove(&self, key: &str) -> Result<Option<serde_yaml::Value>, ExdirError> {
+        let mut map = self.load()?;
+        let removed = map.remove(key);
+        self.save(&map)?;
+        Ok(removed)
+    }
+
+    fn iter(&self) -> Result<impl Iterator<Item = (String, serde_yaml::Value)>, ExdirError> {
+        Ok(self.load()?.into_iter())
+    }
+}
+
 #[derive(Debug)]
-struct Group;
+struct Group {
+    object: Object,
+}
 
 #[derive(Debug)]
-struct Dataset;
+struct Dataset {
+    object: Object,
+    shape: Vec<u64>,
+}
+
+impl Dataset {
+    fn data_path(&self) -> PathBuf {
+        self.object.directory().join(DATA_FILENAME)
+    }
+
+    /// Reads the dataset's `data.npy` back into a typed `Vec<T>`.
+    fn read<T: npyz::Deserialize>(&self) -> Result<Vec<T>, ExdirError> {
+        let bytes = self.object.fs.read(&self.data_path())?;
+        Ok(npyz::NpyFile::new(&bytes[..])?.into_vec::<T>()?)
+    }
+
+    /// Convenience accessor for the common case of `f64` data.
+    fn data(&self) -> Result<Vec<f64>, ExdirError> {
+        self.read::<f64>()
+    }
+
+    fn attrs(&self) -> Attributes {
+        self.object.attrs()
+    }
+}
+
+impl Group {
+    fn attrs(&self) -> Attributes {
+        self.object.attrs()
+    }
+}
 
 trait HasLeaves {
-    fn create_dataset(&self, name: &str) -> Dataset;
-    fn create_group(&self, name: &str) -> Group;
+    fn create_dataset<T>(
+        &self,
+        name: &str,
+        shape: &[u64],
+        data: Option<&[T]>,
+        dtype: Option<npyz::DType>,
+        fill_value: Option<T>,
+    ) -> Result<Dataset, ExdirError>
+    where
+        T: npyz::AutoSerialize + Clone;
+
+    fn create_group(&self, name: &str) -> Result<Group, ExdirError>;
+
+    /// Creates a raw directory (no `exdir.yaml`) for storing arbitrary files
+    /// that don't fit the exdir data model. Fails if `name` is already taken.
+    fn create_raw(&self, name: &str) -> Result<PathBuf, ExdirError>;
+
+    /// Like `create_raw`, but returns the existing directory instead of
+    /// erroring if `name` was already created.
+    fn require_raw(&self, name: &str) -> Result<PathBuf, ExdirError>;
+}
+
+fn create_raw_in(parent: &Object, name: &str) -> Result<PathBuf, ExdirError> {
+    let raw_parent = parent.directory().join(RAW_FOLDER_NAME);
+    validate_name(parent.fs.as_ref(), parent.naming_rule, &raw_parent, name)?;
+    let raw_directory = raw_parent.join(name);
+    if parent.fs.exists(&raw_directory) {
+        return Err(ExdirError::PathExists(raw_directory));
+    }
+    parent.fs.create_dir_all(&raw_directory)?;
+    Ok(raw_directory)
+}
+
+fn require_raw_in(parent: &Object, name: &str) -> Result<PathBuf, ExdirError> {
+    let raw_directory = parent.directory().join(RAW_FOLDER_NAME).join(name);
+    if parent.fs.exists(&raw_directory) {
+        return Ok(raw_directory);
+    }
+    create_raw_in(parent, name)
+}
+
+fn create_group_in(parent: &Object, name: &str) -> Result<Group, ExdirError> {
+    validate_name(
+        parent.fs.as_ref(),
+        parent.naming_rule,
+        &parent.directory(),
+        name,
+    )?;
+    let object = Object::new(
+        &parent.root_directory,
+        &parent.relative_path,
+        name,
+        None,
+        parent.fs.clone(),
+        parent.naming_rule,
+    );
+    _create_object_directory(
+        parent.fs.as_ref(),
+        &object.directory(),
+        &Metadata::new(ObjectType::Group),
+    )?;
+    Ok(Group { object })
+}
+
+fn create_dataset_in<T>(
+    parent: &Object,
+    name: &str,
+    shape: &[u64],
+    data: Option<&[T]>,
+    dtype: Option<npyz::DType>,
+    fill_value: Option<T>,
+) -> Result<Dataset, ExdirError>
+where
+    T: npyz::AutoSerialize + Clone,
+{
+    validate_name(
+        parent.fs.as_ref(),
+        parent.naming_rule,
+        &parent.directory(),
+        name,
+    )?;
+    let object = Object::new(
+        &parent.root_directory,
+        &parent.relative_path,
+        name,
+        None,
+        parent.fs.clone(),
+        parent.naming_rule,
+    );
+    let directory = object.directory();
+    _create_object_directory(
+        parent.fs.as_ref(),
+        &directory,
+        &Metadata::new(ObjectType::Dataset),
+    )?;
+
+    if let Err(err) = write_dataset_npy(
+        parent.fs.as_ref(),
+        &directory,
+        shape,
+        data,
+        dtype,
+        fill_value,
+    ) {
+        // Don't leave behind a directory with an `exdir.yaml` claiming to be
+        // a dataset but no `data.npy` to back it.
+        let _ = parent.fs.remove_dir(&directory);
+        return Err(err);
+    }
+
+    Ok(Dataset {
+        object,
+        shape: shape.to_vec(),
+    })
+}
+
+fn write_dataset_npy<T>(
+    fs: &dyn Fs,
+    directory: &Path,
+    shape: &[u64],
+    data: Option<&[T]>,
+    dtype: Option<npyz::DType>,
+    fill_value: Option<T>,
+) -> Result<(), ExdirError>
+where
+    T: npyz::AutoSerialize + Clone,
+{
+    let num_elements: usize = shape.iter().product::<u64>() as usize;
+    let values: Vec<T> = match data {
+        Some(values) => values.to_vec(),
+        None => {
+            let fill_value = fill_value.ok_or(ExdirError::MissingData)?;
+            std::iter::repeat_n(fill_value, num_elements).collect()
+        }
+    };
+
+    let mut out_buf = Vec::new();
+    let options = npyz::WriteOptions::new().shape(shape);
+    let mut writer = match dtype {
+        Some(dtype) => options.dtype(dtype).writer(&mut out_buf).begin_nd()?,
+        None => options.default_dtype().writer(&mut out_buf).begin_nd()?,
+    };
+    writer.extend(values)?;
+    writer.finish()?;
+
+    fs.write(&directory.join(DATA_FILENAME), &out_buf)?;
+    Ok(())
 }
 
 impl HasLeaves for Group {
-    // TODO fillvalue can be any numeric type
-    // fillvalue: Option<f64>
-    fn create_dataset(&self, name: &str) -> Dataset {
-        Dataset
+    fn create_dataset<T>(
+        &self,
+        name: &str,
+        shape: &[u64],
+        data: Option<&[T]>,
+        dtype: Option<npyz::DType>,
+        fill_value: Option<T>,
+    ) -> Result<Dataset, ExdirError>
+    where
+        T: npyz::AutoSerialize + Clone,
+    {
+        create_dataset_in(&self.object, name, shape, data, dtype, fill_value)
     }
 
-    fn create_group(&self, name: &str) -> Group {
-        Group
+    fn create_group(&self, name: &str) -> Result<Group, ExdirError> {
+        create_group_in(&self.object, name)
+    }
+
+    fn create_raw(&self, name: &str) -> Result<PathBuf, ExdirError> {
+        create_raw_in(&self.object, name)
+    }
+
+    fn require_raw(&self, name: &str) -> Result<PathBuf, ExdirError> {
+        require_raw_in(&self.object, name)
     }
 }
 
@@ -86,40 +511,108 @@ enum OpenMode {
 
 const RECOGNIZED_MODES: [&str; 7] = ["a", "r", "r+", "w", "w-", "x", "a"];
 
-// enum NamingRule {
-//     Simple,
-//     Strict,
-//     Thorough,
-//     None,
-// }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NamingRule {
+    Simple,
+    Strict,
+    Thorough,
+    None,
+}
+
+/// Validates `name` as a child of `parent_directory` under `rule`.
+///
+/// `Simple` rejects path separators and names that collide with reserved
+/// filenames (`exdir.yaml`, `attributes.yaml`, `__raw__`). `Strict` also
+/// requires lowercase ASCII. `Thorough` additionally lists `parent_directory`
+/// and rejects a case-insensitive collision with an existing entry, since
+/// some filesystems are case-insensitive. `None` allows anything.
+fn validate_name(
+    fs: &dyn Fs,
+    rule: NamingRule,
+    parent_directory: &Path,
+    name: &str,
+) -> Result<(), ExdirError> {
+    if rule == NamingRule::None {
+        return Ok(());
+    }
+
+    let is_reserved =
+        name == META_FILENAME || name == ATTRIBUTES_FILENAME || name == RAW_FOLDER_NAME;
+    let has_separator = name.contains('/') || name.contains('\\');
+    if name.is_empty() || is_reserved || has_separator {
+        return Err(ExdirError::InvalidName(name.to_string()));
+    }
+    if rule == NamingRule::Simple {
+        return Ok(());
+    }
+
+    let is_lowercase_ascii = name.is_ascii() && !name.chars().any(|c| c.is_ascii_uppercase());
+    if !is_lowercase_ascii {
+        return Err(ExdirError::InvalidName(name.to_string()));
+    }
+    if rule == NamingRule::Strict {
+        return Ok(());
+    }
+
+    let existing = fs.list_dir(parent_directory)?;
+    if existing
+        .iter()
+        .any(|entry| entry.eq_ignore_ascii_case(name))
+    {
+        return Err(ExdirError::NameCollision(name.to_string()));
+    }
+    Ok(())
+}
 
 #[derive(Debug)]
-struct File;
+struct File {
+    object: Object,
+}
+
+/// Serializes `value` as YAML into a temporary file alongside `path`, then
+/// renames it into place. The rename is atomic, so `path` is only ever
+/// observed complete or absent, never a partially-written file left behind
+/// by an interrupted write.
+fn write_yaml_atomically<T: Serialize>(
+    fs: &dyn Fs,
+    path: &Path,
+    value: &T,
+) -> Result<(), ExdirError> {
+    let directory = path
+        .parent()
+        .expect("a metadata/attribute path always has a parent directory");
+    let tmp_path = directory.join(format!(
+        ".{}.tmp",
+        path.file_name().unwrap().to_string_lossy()
+    ));
 
-fn _create_object_directory(directory: &PathBuf, metadata: &Metadata) {
-    if directory.exists() {
-        eprintln!("The directory '{:?}' already exists", directory);
-        panic!();
+    let mut contents = Vec::new();
+    serde_yaml::to_writer(&mut contents, value)?;
+    fs.write(&tmp_path, &contents)?;
+    fs.rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn _create_object_directory(
+    fs: &dyn Fs,
+    directory: &Path,
+    metadata: &Metadata,
+) -> Result<(), ExdirError> {
+    if fs.exists(directory) {
+        return Err(ExdirError::PathExists(directory.to_path_buf()));
     }
-    create_dir_all(directory.as_path()).unwrap();
-    let meta_filename = directory.join(META_FILENAME);
-    serde_yaml::to_writer(
-        BufWriter::new(std::fs::File::create(meta_filename.as_path()).unwrap()),
-        metadata,
-    )
-    .unwrap();
+    fs.create_dir_all(directory)?;
+    write_yaml_atomically(fs, &directory.join(META_FILENAME), metadata)?;
+    Ok(())
 }
 
-fn is_nonraw_object_directory(directory: &PathBuf) -> bool {
+fn is_nonraw_object_directory(fs: &dyn Fs, directory: &Path) -> Result<bool, ExdirError> {
     let meta_filename = directory.join(META_FILENAME);
-    if !meta_filename.exists() {
-        return false;
+    if !fs.exists(&meta_filename) {
+        return Ok(false);
     }
-    let _meta_data: Metadata = serde_yaml::from_reader(BufReader::new(
-        std::fs::File::open(meta_filename.as_path()).unwrap(),
-    ))
-    .unwrap();
-    true
+    let _meta_data: Metadata = serde_yaml::from_slice(&fs.read(&meta_filename)?)?;
+    Ok(true)
 }
 
 impl File {
@@ -127,16 +620,28 @@ impl File {
         directory: &str,
         mode: Option<&str>,
         allow_remove: Option<bool>,
-    ) -> Result<Self, std::io::Error> {
+        naming_rule: Option<NamingRule>,
+    ) -> Result<Self, ExdirError> {
+        Self::new_with_fs(directory, mode, allow_remove, naming_rule, Rc::new(RealFs))
+    }
+
+    fn new_with_fs(
+        directory: &str,
+        mode: Option<&str>,
+        allow_remove: Option<bool>,
+        naming_rule: Option<NamingRule>,
+        fs: Rc<dyn Fs>,
+    ) -> Result<Self, ExdirError> {
         let allow_remove = allow_remove.unwrap_or(false);
+        // Match the pre-validation behavior for callers who don't opt in.
+        let naming_rule = naming_rule.unwrap_or(NamingRule::None);
 
         let mode = mode.unwrap_or("a");
         if !RECOGNIZED_MODES.contains(&mode) {
-            eprintln!(
-                "IO mode {} not recognized, mode must be one of {:?}",
-                mode, RECOGNIZED_MODES
-            );
-            panic!();
+            return Err(ExdirError::InvalidMode(
+                mode.to_string(),
+                RECOGNIZED_MODES.to_vec(),
+            ));
         }
 
         let directory = PathBuf::from(directory);
@@ -154,45 +659,32 @@ impl File {
 
         // no plugins in this implementation
 
-        // no (customizable) name validation in this implementation
-
-        let already_exists = directory.exists();
-        if already_exists {
-            if !is_nonraw_object_directory(&directory) {
-                eprintln!(
-                    "Path '{:?}' already exists, but is not a valid exdir file.",
-                    directory
-                );
-                panic!();
-            }
+        let already_exists = fs.exists(&directory);
+        if already_exists && !is_nonraw_object_directory(fs.as_ref(), &directory)? {
+            return Err(ExdirError::NotAnExdirFile(directory));
         }
 
         let mut should_create_directory = false;
 
         match mode {
-            "r" => {
-                if !already_exists {
-                    panic!()
-                }
-            }
-            "r+" => {
+            "r" | "r+" => {
                 if !already_exists {
-                    panic!()
+                    return Err(ExdirError::FileNotFound(directory));
                 }
             }
             "w" => {
                 if already_exists {
                     if allow_remove {
-                        remove_dir_all(&directory)?;
+                        fs.remove_dir(&directory)?;
                     } else {
-                        panic!()
+                        return Err(ExdirError::PathExists(directory));
                     }
                 }
                 should_create_directory = true;
             }
             "w-" | "x" => {
                 if already_exists {
-                    panic!()
+                    return Err(ExdirError::PathExists(directory));
                 }
                 should_create_directory = true;
             }
@@ -201,29 +693,59 @@ impl File {
                     should_create_directory = true;
                 }
             }
-            _ => panic!(),
+            _ => unreachable!("mode was already validated against RECOGNIZED_MODES"),
         }
 
         if should_create_directory {
-            // TODO self.name_validation(directory.parent, directory.name)
-            _create_object_directory(&directory, &Metadata::new(ObjectType::File));
+            if let (Some(parent), Some(file_name)) = (directory.parent(), directory.file_name()) {
+                validate_name(
+                    fs.as_ref(),
+                    naming_rule,
+                    parent,
+                    &file_name.to_string_lossy(),
+                )?;
+            }
+            _create_object_directory(fs.as_ref(), &directory, &Metadata::new(ObjectType::File))?;
         }
 
-        Ok(File {})
+        let object = Object::new(&directory, Path::new(""), "", None, fs, naming_rule);
+        Ok(File { object })
     }
 
     fn default(directory: &str) -> Self {
-        Self::new(directory, None, Some(false)).unwrap()
+        Self::new(directory, None, Some(false), None).unwrap()
+    }
+
+    fn attrs(&self) -> Attributes {
+        self.object.attrs()
     }
 }
 
 impl HasLeaves for File {
-    fn create_dataset(&self, name: &str) -> Dataset {
-        Dataset
+    fn create_dataset<T>(
+        &self,
+        name: &str,
+        shape: &[u64],
+        data: Option<&[T]>,
+        dtype: Option<npyz::DType>,
+        fill_value: Option<T>,
+    ) -> Result<Dataset, ExdirError>
+    where
+        T: npyz::AutoSerialize + Clone,
+    {
+        create_dataset_in(&self.object, name, shape, data, dtype, fill_value)
+    }
+
+    fn create_group(&self, name: &str) -> Result<Group, ExdirError> {
+        create_group_in(&self.object, name)
     }
 
-    fn create_group(&self, name: &str) -> Group {
-        Group
+    fn create_raw(&self, name: &str) -> Result<PathBuf, ExdirError> {
+        create_raw_in(&self.object, name)
+    }
+
+    fn require_raw(&self, name: &str) -> Result<PathBuf, ExdirError> {
+        require_raw_in(&self.object, name)
     }
 }
 
@@ -306,7 +828,7 @@ mod tests {
         let testdir = testpathbase.path().join("exdir_dir");
         let testfilep = testpathbase.path().join("test.exdir");
         create_dir_all(testdir.clone()).unwrap();
-        let testfile = Some(File::new(testfilep.to_str().unwrap(), Some("w"), None).unwrap());
+        let testfile = Some(File::new(testfilep.to_str().unwrap(), Some("w"), None, None).unwrap());
         FixtureExdir {
             testpathbase,
             testdir: Some(testdir),
@@ -319,11 +841,21 @@ mod tests {
     fn exdir_tmpfile() -> FixtureExdir {
         let testpathbase = make_tempdir();
         let testfilep = Some(testpathbase.path().join("test.exdir"));
+        let testfile = Some(
+            File::new_with_fs(
+                "test.exdir",
+                Some("w"),
+                None,
+                None,
+                Rc::new(FakeFs::default()),
+            )
+            .unwrap(),
+        );
         FixtureExdir {
             testpathbase,
             testdir: None,
             testfilep,
-            testfile: Some(File::new("", Some("w"), None).unwrap()),
+            testfile,
         }
     }
 
@@ -333,7 +865,14 @@ mod tests {
     #[rstest]
     fn object_init(setup_teardown_folder: FixtureExdir) {
         let tdir = setup_teardown_folder.testdir.unwrap();
-        let obj = Object::new(tdir.as_path(), Path::new(""), "test_object", None);
+        let obj = Object::new(
+            tdir.as_path(),
+            Path::new(""),
+            "test_object",
+            None,
+            Rc::new(RealFs),
+            NamingRule::Thorough,
+        );
         assert_eq!(obj.root_directory, tdir);
         assert_eq!(obj.object_name, "test_object".to_string());
         assert_eq!(obj.parent_path, PathBuf::from(""));
@@ -344,12 +883,186 @@ mod tests {
 
     #[rstest]
     fn open_object(exdir_tmpfile: FixtureExdir) {
-        let grp = exdir_tmpfile.testfile.unwrap().create_group("test");
-        let _grp2 = grp.create_group("test2");
+        let grp = exdir_tmpfile
+            .testfile
+            .unwrap()
+            .create_group("test")
+            .unwrap();
+        let _grp2 = grp.create_group("test2").unwrap();
     }
 
     #[rstest]
-    fn object_attrs(setup_teardown_file: FixtureExdir) {}
+    fn create_group_and_dataset_with_fake_fs() {
+        let file = File::new_with_fs(
+            "test.exdir",
+            Some("w"),
+            None,
+            None,
+            Rc::new(FakeFs::default()),
+        )
+        .unwrap();
+        let grp = file.create_group("grp").unwrap();
+        let dataset = grp
+            .create_dataset("data", &[3], Some(&[1.0, 2.0, 3.0]), None, None)
+            .unwrap();
+        assert_eq!(dataset.data().unwrap(), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[rstest]
+    fn create_dataset_cleans_up_directory_on_write_failure() {
+        let file = File::new_with_fs(
+            "test.exdir",
+            Some("w"),
+            None,
+            None,
+            Rc::new(FakeFs::default()),
+        )
+        .unwrap();
+
+        // shape wants 6 elements but only 3 are supplied, so the npy writer
+        // fails in `writer.finish()`.
+        let result = file.create_dataset("bad", &[2, 3], Some(&[1.0, 2.0, 3.0]), None, None);
+        assert!(result.is_err());
+        assert!(!file.object.fs.exists(&file.object.directory().join("bad")));
+    }
+
+    #[rstest]
+    fn create_group_rejects_invalid_names() {
+        let file = File::new_with_fs(
+            "test.exdir",
+            Some("w"),
+            None,
+            Some(NamingRule::Thorough),
+            Rc::new(FakeFs::default()),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            file.create_group(META_FILENAME),
+            Err(ExdirError::InvalidName(_))
+        ));
+        assert!(matches!(
+            file.create_group("a/b"),
+            Err(ExdirError::InvalidName(_))
+        ));
+        assert!(matches!(
+            file.create_group("Grp"),
+            Err(ExdirError::InvalidName(_))
+        ));
+
+        file.create_group("grp").unwrap();
+        assert!(matches!(
+            file.create_group("grp"),
+            Err(ExdirError::NameCollision(_))
+        ));
+
+        let lenient = File::new_with_fs(
+            "lenient.exdir",
+            Some("w"),
+            None,
+            Some(NamingRule::None),
+            Rc::new(FakeFs::default()),
+        )
+        .unwrap();
+        lenient.create_group(RAW_FOLDER_NAME).unwrap();
+    }
+
+    #[rstest]
+    fn thorough_naming_rule_catches_case_insensitive_collisions() {
+        let fs: Rc<dyn Fs> = Rc::new(FakeFs::default());
+        let lenient = File::new_with_fs(
+            "test.exdir",
+            Some("w"),
+            None,
+            Some(NamingRule::Simple),
+            fs.clone(),
+        )
+        .unwrap();
+        lenient.create_group("Grp").unwrap();
+
+        let thorough = File::new_with_fs(
+            "test.exdir",
+            Some("a"),
+            None,
+            Some(NamingRule::Thorough),
+            fs,
+        )
+        .unwrap();
+        assert!(matches!(
+            thorough.create_group("grp"),
+            Err(ExdirError::NameCollision(_))
+        ));
+    }
+
+    #[rstest]
+    fn create_dataset_writes_npy(setup_teardown_file: FixtureExdir) {
+        let file = setup_teardown_file.testfile.unwrap();
+        let dataset = file
+            .create_dataset(
+                "mydata",
+                &[2, 3],
+                Some(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+                None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(dataset.data().unwrap(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[rstest]
+    fn object_attrs(setup_teardown_file: FixtureExdir) {
+        let attrs = setup_teardown_file.testfile.unwrap().attrs();
+        assert!(!attrs.contains("temperature").unwrap());
+
+        attrs
+            .set("temperature", serde_yaml::Value::from(23.5))
+            .unwrap();
+        assert!(attrs.contains("temperature").unwrap());
+        assert_eq!(
+            attrs.get("temperature").unwrap(),
+            Some(serde_yaml::Value::from(23.5))
+        );
+        assert_eq!(
+            attrs.iter().unwrap().collect::<Vec<_>>(),
+            vec![("temperature".to_string(), serde_yaml::Value::from(23.5))]
+        );
+
+        let removed = attrs.remove("temperature").unwrap();
+        assert_eq!(removed, Some(serde_yaml::Value::from(23.5)));
+        assert!(!attrs.contains("temperature").unwrap());
+    }
+
+    #[rstest]
+    fn group_and_dataset_attrs(setup_teardown_file: FixtureExdir) {
+        let file = setup_teardown_file.testfile.unwrap();
+
+        let grp = file.create_group("grp").unwrap();
+        let grp_attrs = grp.attrs();
+        grp_attrs
+            .set("label", serde_yaml::Value::from("a group"))
+            .unwrap();
+        assert_eq!(
+            grp_attrs.get("label").unwrap(),
+            Some(serde_yaml::Value::from("a group"))
+        );
+
+        let dataset = grp
+            .create_dataset("data", &[3], Some(&[1.0, 2.0, 3.0]), None, None)
+            .unwrap();
+        let dataset_attrs = dataset.attrs();
+        dataset_attrs
+            .set("unit", serde_yaml::Value::from("volt"))
+            .unwrap();
+        assert_eq!(
+            dataset_attrs.get("unit").unwrap(),
+            Some(serde_yaml::Value::from("volt"))
+        );
+
+        // A group's attributes are stored independently of those of a dataset
+        // inside it.
+        assert!(!grp_attrs.contains("unit").unwrap());
+        assert!(!dataset_attrs.contains("label").unwrap());
+    }
 
     #[rstest]
     fn object_meta(setup_teardown_file: FixtureExdir) {}
@@ -358,7 +1071,56 @@ mod tests {
     fn object_directory(setup_teardown_file: FixtureExdir) {}
 
     #[rstest]
-    fn object_create_raw(setup_teardown_file: FixtureExdir) {}
+    fn object_create_raw(setup_teardown_file: FixtureExdir) {
+        let file = setup_teardown_file.testfile.unwrap();
+
+        let raw_path = file.create_raw("blob").unwrap();
+        assert!(raw_path.ends_with(PathBuf::from(RAW_FOLDER_NAME).join("blob")));
+        assert!(!is_nonraw_object_directory(file.object.fs.as_ref(), &raw_path).unwrap());
+
+        std::fs::write(raw_path.join("payload.bin"), b"hello").unwrap();
+        assert_eq!(
+            std::fs::read(raw_path.join("payload.bin")).unwrap(),
+            b"hello"
+        );
+
+        assert!(matches!(
+            file.create_raw("blob"),
+            Err(ExdirError::PathExists(_))
+        ));
+
+        assert_eq!(file.require_raw("blob").unwrap(), raw_path);
+
+        let other = file.require_raw("other").unwrap();
+        assert!(other.is_dir());
+    }
+
+    #[rstest]
+    fn thorough_naming_rule_catches_case_insensitive_raw_collisions() {
+        let fs: Rc<dyn Fs> = Rc::new(FakeFs::default());
+        let lenient = File::new_with_fs(
+            "test.exdir",
+            Some("w"),
+            None,
+            Some(NamingRule::Simple),
+            fs.clone(),
+        )
+        .unwrap();
+        lenient.create_raw("Blob").unwrap();
+
+        let thorough = File::new_with_fs(
+            "test.exdir",
+            Some("a"),
+            None,
+            Some(NamingRule::Thorough),
+            fs,
+        )
+        .unwrap();
+        assert!(matches!(
+            thorough.create_raw("blob"),
+            Err(ExdirError::NameCollision(_))
+        ));
+    }
 
     #[test]
     fn npy_example() -> std::io::Result<()> {